@@ -0,0 +1,94 @@
+//! Minimal ABI encoding/decoding helpers.
+//!
+//! Only the subset needed to build and tear apart the handful of Solidity
+//! calls used by the signature verification paths (`isValidSignature` and
+//! the ERC-6492 wrapper) is implemented here; this is not a general purpose
+//! ABI codec.
+
+use alloy_primitives::{Address, U256};
+
+fn pad32(bytes: &[u8]) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    let padding = (32 - out.len() % 32) % 32;
+    out.extend(std::iter::repeat(0u8).take(padding));
+    out
+}
+
+/// ABI-encodes a call to `isValidSignature(bytes32 hash, bytes signature)`
+/// prefixed with `selector`.
+pub fn encode_is_valid_signature_call(selector: [u8; 4], hash: [u8; 32], signature: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(4 + 32 + 32 + 32 + signature.len());
+    data.extend_from_slice(&selector);
+    data.extend_from_slice(&hash);
+    data.extend_from_slice(&U256::from(64u64).to_be_bytes::<32>());
+    data.extend_from_slice(&U256::from(signature.len() as u64).to_be_bytes::<32>());
+    data.extend_from_slice(&pad32(signature));
+    data
+}
+
+/// Reads a dynamic `bytes` value encoded at `offset` within `data`.
+///
+/// `offset` and the length word are attacker-controlled (they come straight
+/// off the wire in an ERC-6492 signature), so every arithmetic step is
+/// checked: a word that doesn't fit in a `usize`, or an offset/length that
+/// would overflow while computing the slice bounds, is treated the same as
+/// an out-of-range slice and yields `None` rather than panicking.
+fn read_bytes(data: &[u8], offset: usize) -> Option<Vec<u8>> {
+    let len_word = data.get(offset..offset.checked_add(32)?)?;
+    let len = U256::from_be_slice(len_word).checked_to::<usize>()?;
+    let start = offset.checked_add(32)?;
+    let end = start.checked_add(len)?;
+    data.get(start..end).map(|s| s.to_vec())
+}
+
+/// ABI-encodes the `(address signer, bytes32 hash, address factory, bytes
+/// factoryCalldata, bytes innerSignature)` constructor arguments for the
+/// ERC-6492 universal signature validator helper contract.
+pub fn encode_erc6492_validator_args(
+    signer: Address,
+    hash: [u8; 32],
+    factory: Address,
+    factory_calldata: &[u8],
+    inner_signature: &[u8],
+) -> Vec<u8> {
+    const HEAD_WORDS: u64 = 5;
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&[0u8; 12]);
+    data.extend_from_slice(signer.as_slice());
+    data.extend_from_slice(&hash);
+    data.extend_from_slice(&[0u8; 12]);
+    data.extend_from_slice(factory.as_slice());
+
+    let factory_calldata_offset = U256::from(HEAD_WORDS * 32);
+    data.extend_from_slice(&factory_calldata_offset.to_be_bytes::<32>());
+
+    let inner_signature_offset =
+        factory_calldata_offset + U256::from(32 + pad32(factory_calldata).len() as u64);
+    data.extend_from_slice(&inner_signature_offset.to_be_bytes::<32>());
+
+    data.extend_from_slice(&U256::from(factory_calldata.len() as u64).to_be_bytes::<32>());
+    data.extend_from_slice(&pad32(factory_calldata));
+
+    data.extend_from_slice(&U256::from(inner_signature.len() as u64).to_be_bytes::<32>());
+    data.extend_from_slice(&pad32(inner_signature));
+
+    data
+}
+
+/// ABI-decodes the `(address factory, bytes factoryCalldata, bytes
+/// innerSignature)` tuple used by the ERC-6492 signature wrapper.
+pub fn decode_erc6492_wrapper(body: &[u8]) -> Option<(Address, Vec<u8>, Vec<u8>)> {
+    if body.len() < 96 {
+        return None;
+    }
+
+    let factory = Address::from_slice(&body[12..32]);
+    let factory_calldata_offset = U256::from_be_slice(&body[32..64]).checked_to::<usize>()?;
+    let inner_signature_offset = U256::from_be_slice(&body[64..96]).checked_to::<usize>()?;
+
+    let factory_calldata = read_bytes(body, factory_calldata_offset)?;
+    let inner_signature = read_bytes(body, inner_signature_offset)?;
+
+    Some((factory, factory_calldata, inner_signature))
+}
@@ -1,10 +1,11 @@
-use std::convert::TryInto;
-
 use alloy_primitives::{Address, B256, U256};
-use eip_712_derive::{sign_typed, DomainSeparator, Eip712Domain, MemberVisitor, StructType};
+#[cfg(not(feature = "pure-rust"))]
+use eip_712_derive::{sign_typed, MemberVisitor, StructType};
+use eip_712_derive::{DomainSeparator, Eip712Domain};
 use keccak_hash::keccak;
 use neon::prelude::Finalize;
-use secp256k1::SecretKey;
+
+use crate::backend::{self, SecretKey};
 
 pub struct AttestationSigner {
     subgraph_deployment_id: B256,
@@ -14,6 +15,21 @@ pub struct AttestationSigner {
 
 impl Finalize for AttestationSigner {}
 
+/// Builds the `EIP712Domain` shared by attestation signing and verification,
+/// so both sides hash over an identical domain separator.
+fn eip712_domain(chain_id: U256, dispute_manager: Address) -> Eip712Domain {
+    let salt = "0xa070ffb1cd7409649bf77822cce74495468e06dbfaef09556838bf188679b9c2"
+        .parse::<B256>()
+        .unwrap();
+    Eip712Domain {
+        name: "Graph Protocol".to_owned(),
+        version: "0".to_owned(),
+        chain_id: eip_712_derive::U256(chain_id.to_be_bytes()),
+        verifying_contract: eip_712_derive::Address(*dispute_manager.0),
+        salt: salt.0,
+    }
+}
+
 impl AttestationSigner {
     pub fn new(
         chain_id: U256,
@@ -21,16 +37,7 @@ impl AttestationSigner {
         signer: SecretKey,
         subgraph_deployment_id: B256,
     ) -> Self {
-        let salt = "0xa070ffb1cd7409649bf77822cce74495468e06dbfaef09556838bf188679b9c2"
-            .parse::<B256>()
-            .unwrap();
-        let domain = Eip712Domain {
-            name: "Graph Protocol".to_owned(),
-            version: "0".to_owned(),
-            chain_id: eip_712_derive::U256(chain_id.to_be_bytes()),
-            verifying_contract: eip_712_derive::Address(*dispute_manager.0),
-            salt: salt.0,
-        };
+        let domain = eip712_domain(chain_id, dispute_manager);
         Self {
             domain_separator: DomainSeparator::new(&domain),
             signer,
@@ -42,15 +49,7 @@ impl AttestationSigner {
         let request_cid = keccak(request).to_fixed_bytes().into();
         let response_cid = keccak(response).to_fixed_bytes().into();
 
-        let receipt = Receipt {
-            request_cid,
-            response_cid,
-            subgraph_deployment_id: self.subgraph_deployment_id,
-        };
-
-        // Unwrap: This can only fail if the SecretKey is invalid.
-        // Since it is of type SecretKey it has already been validated.
-        let (rs, v) = sign_typed(&self.domain_separator, &receipt, self.signer.as_ref()).unwrap();
+        let (rs, v) = self.sign_receipt(request_cid, response_cid);
 
         let r = rs[0..32].try_into().unwrap();
         let s = rs[32..64].try_into().unwrap();
@@ -64,14 +63,139 @@ impl AttestationSigner {
             s,
         }
     }
+
+    #[cfg(not(feature = "pure-rust"))]
+    fn sign_receipt(&self, request_cid: B256, response_cid: B256) -> ([u8; 64], u8) {
+        let receipt = Receipt {
+            request_cid,
+            response_cid,
+            subgraph_deployment_id: self.subgraph_deployment_id,
+        };
+
+        // Unwrap: This can only fail if the SecretKey is invalid.
+        // Since it is of type SecretKey it has already been validated.
+        sign_typed(&self.domain_separator, &receipt, self.signer.as_ref()).unwrap()
+    }
+
+    // `eip_712_derive::sign_typed` hardwires `secp256k1::SecretKey`, so the
+    // `pure-rust` backend instead signs the digest directly, reconstructed by
+    // `receipt_digest` exactly as `sign_typed` would have hashed it.
+    #[cfg(feature = "pure-rust")]
+    fn sign_receipt(&self, request_cid: B256, response_cid: B256) -> ([u8; 64], u8) {
+        let digest = receipt_digest(
+            &self.domain_separator,
+            request_cid,
+            response_cid,
+            self.subgraph_deployment_id,
+        );
+        let (rs, recovery_id) =
+            backend::sign_recoverable(digest.as_slice().try_into().unwrap(), &self.signer);
+        (rs, recovery_id + 27)
+    }
+}
+
+/// The EIP-712 struct hash preimage for `Receipt`, i.e. `TYPE_HASH` followed
+/// by each of its (already 32-byte) members in declaration order.
+const RECEIPT_TYPE_HASH_PREIMAGE: &[u8] =
+    b"Receipt(bytes32 requestCID,bytes32 responseCID,bytes32 subgraphDeploymentID)";
+
+/// Reconstructs the EIP-712 digest of a `Receipt`, i.e. what
+/// `sign_typed(domain_separator, receipt, ..)` signs over.
+fn receipt_digest(
+    domain_separator: &DomainSeparator,
+    request_cid: B256,
+    response_cid: B256,
+    subgraph_deployment_id: B256,
+) -> B256 {
+    let type_hash = keccak(RECEIPT_TYPE_HASH_PREIMAGE);
+
+    let mut struct_preimage = Vec::with_capacity(4 * 32);
+    struct_preimage.extend_from_slice(type_hash.as_bytes());
+    struct_preimage.extend_from_slice(request_cid.as_slice());
+    struct_preimage.extend_from_slice(response_cid.as_slice());
+    struct_preimage.extend_from_slice(subgraph_deployment_id.as_slice());
+    let struct_hash = keccak(&struct_preimage);
+
+    let mut digest_preimage = Vec::with_capacity(2 + 32 + 32);
+    digest_preimage.extend_from_slice(&[0x19, 0x01]);
+    digest_preimage.extend_from_slice(&domain_separator.0);
+    digest_preimage.extend_from_slice(struct_hash.as_bytes());
+    keccak(&digest_preimage).to_fixed_bytes().into()
+}
+
+/// Verifies attestations produced by [`AttestationSigner`], recovering the
+/// Ethereum address that signed them. This is the inverse operation: given
+/// an `Attestation`, fishermen and consumers can confirm it was produced by
+/// the indexer they expect before acting on the attached query response.
+pub struct AttestationVerifier {
+    subgraph_deployment_id: B256,
+    domain_separator: DomainSeparator,
+}
+
+impl Finalize for AttestationVerifier {}
+
+impl AttestationVerifier {
+    pub fn new(chain_id: U256, dispute_manager: Address, subgraph_deployment_id: B256) -> Self {
+        let domain = eip712_domain(chain_id, dispute_manager);
+        Self {
+            domain_separator: DomainSeparator::new(&domain),
+            subgraph_deployment_id,
+        }
+    }
+
+    pub fn subgraph_deployment_id(&self) -> B256 {
+        self.subgraph_deployment_id
+    }
+
+    /// Recovers the address that produced `attestation`'s signature.
+    ///
+    /// `attestation` may come from a dishonest or buggy counterparty (this
+    /// is exactly what fishermen/disputers use to check a possibly-bad
+    /// indexer response), so a malformed `v`/`r`/`s` is reported as an
+    /// `Err` rather than aborting the process.
+    pub fn recover_signer(&self, attestation: &Attestation) -> Result<Address, &'static str> {
+        let digest = receipt_digest(
+            &self.domain_separator,
+            attestation.request_cid,
+            attestation.response_cid,
+            self.subgraph_deployment_id,
+        );
+
+        let recovery_id = match attestation.v {
+            id @ (0 | 1) => id,
+            id @ (27 | 28) => id - 27,
+            _ => return Err("Invalid recovery id"),
+        };
+        let mut rs = [0u8; 64];
+        rs[..32].copy_from_slice(attestation.r.as_slice());
+        rs[32..].copy_from_slice(attestation.s.as_slice());
+        let signature = backend::recoverable_signature_from_parts(&rs, recovery_id)
+            .map_err(|_| "Invalid signature")?;
+
+        let public_key = backend::recover(digest.as_slice().try_into().unwrap(), &signature)
+            .map_err(|_| "Failed to recover signer")?;
+        let point = backend::uncompressed_point(&public_key);
+        debug_assert_eq!(point[0], 0x04);
+
+        Ok(Address::from_slice(&keccak(&point[1..])[12..]))
+    }
+
+    /// Returns whether `attestation` was signed by `expected_indexer`. A
+    /// malformed attestation is treated as not matching rather than as an
+    /// error, since the caller only cares whether it can trust the response.
+    pub fn verify(&self, attestation: &Attestation, expected_indexer: Address) -> bool {
+        self.recover_signer(attestation) == Ok(expected_indexer)
+    }
 }
 
+#[cfg(not(feature = "pure-rust"))]
 pub struct Receipt {
     request_cid: B256,
     response_cid: B256,
     subgraph_deployment_id: B256,
 }
 
+#[cfg(not(feature = "pure-rust"))]
 impl StructType for Receipt {
     const TYPE_NAME: &'static str = "Receipt";
     fn visit_members<T: MemberVisitor>(&self, visitor: &mut T) {
@@ -0,0 +1,48 @@
+use k256::ecdsa::signature::hazmat::{PrehashSigner, PrehashVerifier};
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+
+pub use k256::ecdsa::SigningKey as SecretKey;
+pub use k256::ecdsa::VerifyingKey as PublicKey;
+
+pub struct RecoverableSignature {
+    signature: Signature,
+    recovery_id: RecoveryId,
+}
+
+pub fn recoverable_signature_from_parts(rs: &[u8; 64], recovery_id: u8) -> Result<RecoverableSignature, ()> {
+    Ok(RecoverableSignature {
+        signature: Signature::from_slice(rs).map_err(|_| ())?,
+        recovery_id: RecoveryId::from_byte(recovery_id).ok_or(())?,
+    })
+}
+
+pub fn recoverable_signature_parts(signature: &RecoverableSignature) -> ([u8; 64], u8) {
+    let mut rs = [0u8; 64];
+    rs.copy_from_slice(&signature.signature.to_bytes());
+    (rs, signature.recovery_id.to_byte())
+}
+
+pub fn recover(digest: &[u8; 32], signature: &RecoverableSignature) -> Result<PublicKey, ()> {
+    VerifyingKey::recover_from_prehash(digest, &signature.signature, signature.recovery_id)
+        .map_err(|_| ())
+}
+
+pub fn verify(digest: &[u8; 32], signature: &RecoverableSignature, public_key: &PublicKey) -> bool {
+    public_key.verify_prehash(digest, &signature.signature).is_ok()
+}
+
+pub fn uncompressed_point(public_key: &PublicKey) -> [u8; 65] {
+    let point = public_key.to_encoded_point(false);
+    let mut out = [0u8; 65];
+    out.copy_from_slice(point.as_bytes());
+    out
+}
+
+pub fn sign_recoverable(digest: &[u8; 32], secret_key: &SecretKey) -> ([u8; 64], u8) {
+    let (signature, recovery_id): (Signature, RecoveryId) =
+        secret_key.sign_prehash_recoverable(digest).unwrap();
+    let mut rs = [0u8; 64];
+    rs.copy_from_slice(&signature.to_bytes());
+    (rs, recovery_id.to_byte())
+}
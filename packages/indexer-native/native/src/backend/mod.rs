@@ -0,0 +1,66 @@
+//! Pluggable ECDSA backend.
+//!
+//! By default this crate signs/recovers/verifies through the C `secp256k1`
+//! bindings for native performance. Enabling the `pure-rust` cargo feature
+//! swaps the internals for the RustCrypto `k256` stack instead, which has no
+//! C dependency and so builds for `wasm32` and cross-compiles without a C
+//! toolchain. [`crate::signature_verification`] and [`crate::attestation`]
+//! are written against this module's small surface so they behave
+//! identically under either backend.
+
+#[cfg(any(not(feature = "pure-rust"), test))]
+mod secp256k1_backend;
+#[cfg(not(feature = "pure-rust"))]
+pub use secp256k1_backend::*;
+
+#[cfg(any(feature = "pure-rust", test))]
+mod k256_backend;
+#[cfg(feature = "pure-rust")]
+pub use k256_backend::*;
+
+// Tests compile both backend modules unconditionally (regardless of which
+// one `pure-rust` selects for the rest of the crate) so they can be
+// exercised side by side; `secp256k1` and `k256` are therefore both
+// always-on `[dev-dependencies]`, independent of the `pure-rust` feature.
+#[cfg(test)]
+mod tests {
+    use keccak_hash::keccak;
+
+    use super::{k256_backend, secp256k1_backend};
+
+    /// The two backends are swappable at compile time via the `pure-rust`
+    /// feature, so for a given key and message they must produce the exact
+    /// same signature bytes and agree on every verification/recovery
+    /// outcome - otherwise switching backends would silently change what
+    /// signatures the indexer accepts.
+    #[test]
+    fn cross_backend_signing_and_verification_parity() {
+        let secret_bytes = [0x42u8; 32];
+        let digest = keccak(b"cross-backend parity check").to_fixed_bytes();
+
+        let secp_key = secp256k1_backend::SecretKey::from_slice(&secret_bytes).unwrap();
+        let k256_key = k256_backend::SecretKey::from_slice(&secret_bytes).unwrap();
+
+        let (secp_rs, secp_recovery_id) = secp256k1_backend::sign_recoverable(&digest, &secp_key);
+        let (k256_rs, k256_recovery_id) = k256_backend::sign_recoverable(&digest, &k256_key);
+
+        assert_eq!(secp_rs, k256_rs, "r || s bytes must be identical across backends");
+        assert_eq!(secp_recovery_id, k256_recovery_id, "recovery id must match across backends");
+
+        let secp_signature =
+            secp256k1_backend::recoverable_signature_from_parts(&secp_rs, secp_recovery_id).unwrap();
+        let k256_signature =
+            k256_backend::recoverable_signature_from_parts(&k256_rs, k256_recovery_id).unwrap();
+
+        let secp_public_key = secp256k1_backend::recover(&digest, &secp_signature).unwrap();
+        let k256_public_key = k256_backend::recover(&digest, &k256_signature).unwrap();
+        assert_eq!(
+            secp256k1_backend::uncompressed_point(&secp_public_key),
+            k256_backend::uncompressed_point(&k256_public_key),
+            "recovered public key must be identical across backends",
+        );
+
+        assert!(secp256k1_backend::verify(&digest, &secp_signature, &secp_public_key));
+        assert!(k256_backend::verify(&digest, &k256_signature, &k256_public_key));
+    }
+}
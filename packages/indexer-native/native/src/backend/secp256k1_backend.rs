@@ -0,0 +1,44 @@
+use lazy_static::lazy_static;
+use secp256k1::ecdsa::RecoveryId;
+use secp256k1::{Message, Secp256k1, SignOnly, VerifyOnly};
+
+pub use secp256k1::ecdsa::RecoverableSignature;
+pub use secp256k1::{PublicKey, SecretKey};
+
+lazy_static! {
+    static ref VERIFY_CTX: Secp256k1<VerifyOnly> = Secp256k1::verification_only();
+    static ref SIGN_CTX: Secp256k1<SignOnly> = Secp256k1::signing_only();
+}
+
+pub fn recoverable_signature_from_parts(rs: &[u8; 64], recovery_id: u8) -> Result<RecoverableSignature, ()> {
+    let recovery_id = RecoveryId::from_i32(recovery_id as i32).map_err(|_| ())?;
+    RecoverableSignature::from_compact(rs, recovery_id).map_err(|_| ())
+}
+
+pub fn recoverable_signature_parts(signature: &RecoverableSignature) -> ([u8; 64], u8) {
+    let (id, rs) = signature.serialize_compact();
+    (rs, id.to_i32() as u8)
+}
+
+pub fn recover(digest: &[u8; 32], signature: &RecoverableSignature) -> Result<PublicKey, ()> {
+    let message = Message::from_slice(digest).unwrap();
+    VERIFY_CTX.recover_ecdsa(&message, signature).map_err(|_| ())
+}
+
+pub fn verify(digest: &[u8; 32], signature: &RecoverableSignature, public_key: &PublicKey) -> bool {
+    let message = Message::from_slice(digest).unwrap();
+    VERIFY_CTX
+        .verify_ecdsa(&message, &signature.to_standard(), public_key)
+        .is_ok()
+}
+
+pub fn uncompressed_point(public_key: &PublicKey) -> [u8; 65] {
+    public_key.serialize_uncompressed()
+}
+
+pub fn sign_recoverable(digest: &[u8; 32], secret_key: &SecretKey) -> ([u8; 64], u8) {
+    let message = Message::from_slice(digest).unwrap();
+    let signature = SIGN_CTX.sign_ecdsa_recoverable(&message, secret_key);
+    let (id, rs) = signature.serialize_compact();
+    (rs, id.to_i32() as u8)
+}
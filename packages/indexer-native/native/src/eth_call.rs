@@ -0,0 +1,58 @@
+use alloy_primitives::Address;
+
+/// A minimal blocking JSON-RPC client used to perform `eth_call`s against an
+/// Ethereum-compatible node.
+///
+/// This is intentionally small: the signature verification paths that use it
+/// only need a single read-only call, so there is no need to pull in a full
+/// provider/middleware stack just to check a smart-contract signature.
+pub struct RpcEthCaller {
+    endpoint: String,
+}
+
+impl RpcEthCaller {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+        }
+    }
+
+    /// Performs an `eth_call` against `to` with the given calldata and
+    /// returns the raw return data.
+    ///
+    /// When `to` is `None` the call is treated as a contract creation
+    /// simulation: `data` is expected to already be `creation_bytecode ++
+    /// constructor_args` and the node is asked to execute it without
+    /// persisting any state.
+    pub fn eth_call(&self, to: Option<Address>, data: Vec<u8>) -> Result<Vec<u8>, String> {
+        let mut call = serde_json::json!({ "data": format!("0x{}", hex::encode(&data)) });
+        if let Some(to) = to {
+            call["to"] = serde_json::Value::String(to.to_string());
+        }
+
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_call",
+            "params": [call, "latest"],
+        });
+
+        let response: serde_json::Value = ureq::post(&self.endpoint)
+            .send_json(request)
+            .map_err(|err| format!("eth_call request failed: {err}"))?
+            .into_json()
+            .map_err(|err| format!("eth_call response was not valid JSON: {err}"))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(format!("eth_call returned an error: {error}"));
+        }
+
+        let result = response
+            .get("result")
+            .and_then(|v| v.as_str())
+            .ok_or("eth_call response is missing a result field")?;
+
+        hex::decode(result.trim_start_matches("0x"))
+            .map_err(|err| format!("eth_call result was not valid hex: {err}"))
+    }
+}
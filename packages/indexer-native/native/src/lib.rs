@@ -1,18 +1,29 @@
+use std::thread;
+
 use alloy_primitives::{Address, Bytes, FixedBytes, B256, U256};
 use neon::prelude::*;
-use secp256k1::{
-    ecdsa::{RecoverableSignature, RecoveryId},
-    SecretKey,
-};
 
+mod abi;
 mod attestation;
+mod backend;
+mod eth_call;
 mod signature_verification;
 
-use attestation::{Attestation, AttestationSigner};
-use signature_verification::SignatureVerifier;
+use attestation::{Attestation, AttestationSigner, AttestationVerifier};
+use backend::SecretKey;
+use signature_verification::{parse_recoverable_signature, HashingMode, SignatureVerifier};
 
 pub struct SignatureVerifierProxy;
 
+/// Parses the Neon-side hashing mode argument: `"raw"` (the default) for
+/// bare keccak, `"eip191-personal"` for the `personal_sign` prefixed form.
+fn parse_hashing_mode(mode: &str) -> HashingMode {
+    match mode {
+        "eip191-personal" => HashingMode::Eip191Personal,
+        _ => HashingMode::Raw,
+    }
+}
+
 fn signature_verifier_new(mut cx: FunctionContext) -> JsResult<JsBox<SignatureVerifier>> {
     let address: Address = cx.argument::<JsString>(0)?.value(&mut cx).parse().unwrap();
     Ok(cx.boxed(SignatureVerifier::new(address)))
@@ -22,14 +33,110 @@ fn signature_verifier_verify(mut cx: FunctionContext) -> JsResult<JsBoolean> {
     let this = cx.argument::<JsBox<SignatureVerifier>>(0)?;
     let message: Bytes = cx.argument::<JsString>(1)?.value(&mut cx).parse().unwrap();
     let signature: FixedBytes<65> = cx.argument::<JsString>(2)?.value(&mut cx).parse().unwrap();
-    let recovery_id = signature[64] as i32;
-    let recovery_id = match recovery_id {
-        0 | 1 => RecoveryId::from_i32(recovery_id).unwrap(),
-        27 | 28 => RecoveryId::from_i32(recovery_id - 27).unwrap(),
-        _ => panic!("Invalid recovery id"),
+    let mode = match cx.argument_opt(3) {
+        Some(mode) => parse_hashing_mode(&mode.downcast_or_throw::<JsString, _>(&mut cx)?.value(&mut cx)),
+        None => HashingMode::Raw,
+    };
+    let signature = parse_recoverable_signature(&signature).unwrap();
+    Ok(cx.boolean(this.verify(&message, &signature, mode).unwrap()))
+}
+
+/// Verifies many `(message, signature)` pairs against one `SignatureVerifier`
+/// in a single call, amortizing the JS/native boundary crossing over the
+/// whole batch. `messages` and `signatures` must be parallel JS arrays of
+/// equal length.
+fn signature_verifier_verify_batch(mut cx: FunctionContext) -> JsResult<JsArray> {
+    let this = cx.argument::<JsBox<SignatureVerifier>>(0)?;
+    let messages = cx.argument::<JsArray>(1)?.to_vec(&mut cx)?;
+    let signatures = cx.argument::<JsArray>(2)?.to_vec(&mut cx)?;
+    let mode = match cx.argument_opt(3) {
+        Some(mode) => parse_hashing_mode(&mode.downcast_or_throw::<JsString, _>(&mut cx)?.value(&mut cx)),
+        None => HashingMode::Raw,
+    };
+
+    let messages: Vec<Bytes> = messages
+        .into_iter()
+        .map(|v| -> NeonResult<Bytes> {
+            Ok(v.downcast_or_throw::<JsString, _>(&mut cx)?.value(&mut cx).parse().unwrap())
+        })
+        .collect::<NeonResult<_>>()?;
+    let signatures = signatures
+        .into_iter()
+        .map(|v| -> NeonResult<_> {
+            let signature: FixedBytes<65> =
+                v.downcast_or_throw::<JsString, _>(&mut cx)?.value(&mut cx).parse().unwrap();
+            Ok(parse_recoverable_signature(&signature).unwrap())
+        })
+        .collect::<NeonResult<Vec<_>>>()?;
+
+    let message_refs: Vec<&[u8]> = messages.iter().map(|m| m.as_ref()).collect();
+    let results = match this.verify_batch(&message_refs, &signatures, mode) {
+        Ok(results) => results,
+        Err(err) => return cx.throw_error(err),
+    };
+
+    let array = JsArray::new(&mut cx, results.len());
+    for (i, result) in results.into_iter().enumerate() {
+        let value = cx.boolean(result);
+        array.set(&mut cx, i as u32, value)?;
+    }
+    Ok(array)
+}
+
+/// Like [`signature_verifier_verify`], but additionally accepts EIP-1271
+/// smart-contract wallet signatures, which requires an `eth_call` RPC round
+/// trip to the wallet. That call is blocking, so it is run on a background
+/// thread and the result handed back to JS as a `Promise` via a `Channel`,
+/// rather than blocking the single JS event-loop thread for the duration of
+/// the RPC request.
+fn signature_verifier_verify_erc1271(mut cx: FunctionContext) -> JsResult<JsPromise> {
+    let this = (**cx.argument::<JsBox<SignatureVerifier>>(0)?).clone();
+    let message: Bytes = cx.argument::<JsString>(1)?.value(&mut cx).parse().unwrap();
+    let signature: FixedBytes<65> = cx.argument::<JsString>(2)?.value(&mut cx).parse().unwrap();
+    let rpc_endpoint: String = cx.argument::<JsString>(3)?.value(&mut cx);
+    let mode = match cx.argument_opt(4) {
+        Some(mode) => parse_hashing_mode(&mode.downcast_or_throw::<JsString, _>(&mut cx)?.value(&mut cx)),
+        None => HashingMode::Raw,
+    };
+    let signature = parse_recoverable_signature(&signature).unwrap();
+
+    let (deferred, promise) = cx.promise();
+    let channel = cx.channel();
+    thread::spawn(move || {
+        let result = this.verify_erc1271(&message, &signature, mode, &rpc_endpoint);
+        deferred.settle_with(&channel, move |mut cx| match result {
+            Ok(valid) => Ok(cx.boolean(valid)),
+            Err(err) => cx.throw_error(err),
+        });
+    });
+
+    Ok(promise)
+}
+
+/// Like [`signature_verifier_verify_erc1271`], but additionally accepts
+/// ERC-6492 wrapped counterfactual wallet signatures. See that function's
+/// doc comment for why this runs off the JS event-loop thread.
+fn signature_verifier_verify_erc6492(mut cx: FunctionContext) -> JsResult<JsPromise> {
+    let this = (**cx.argument::<JsBox<SignatureVerifier>>(0)?).clone();
+    let message: Bytes = cx.argument::<JsString>(1)?.value(&mut cx).parse().unwrap();
+    let signature: Bytes = cx.argument::<JsString>(2)?.value(&mut cx).parse().unwrap();
+    let rpc_endpoint: String = cx.argument::<JsString>(3)?.value(&mut cx);
+    let mode = match cx.argument_opt(4) {
+        Some(mode) => parse_hashing_mode(&mode.downcast_or_throw::<JsString, _>(&mut cx)?.value(&mut cx)),
+        None => HashingMode::Raw,
     };
-    let signature = RecoverableSignature::from_compact(&signature[..64], recovery_id).unwrap();
-    Ok(cx.boolean(this.verify(&message, &signature).unwrap()))
+
+    let (deferred, promise) = cx.promise();
+    let channel = cx.channel();
+    thread::spawn(move || {
+        let result = this.verify_erc6492(&message, &signature, mode, &rpc_endpoint);
+        deferred.settle_with(&channel, move |mut cx| match result {
+            Ok(valid) => Ok(cx.boolean(valid)),
+            Err(err) => cx.throw_error(err),
+        });
+    });
+
+    Ok(promise)
 }
 
 fn attestation_signer_new(mut cx: FunctionContext) -> JsResult<JsBox<AttestationSigner>> {
@@ -74,14 +181,62 @@ fn attestation_signer_create_attestation(mut cx: FunctionContext) -> JsResult<Js
     Ok(result)
 }
 
+fn attestation_verifier_new(mut cx: FunctionContext) -> JsResult<JsBox<AttestationVerifier>> {
+    let chain_id = cx.argument::<JsNumber>(0)?.value(&mut cx) as u64;
+    let dispute_manager: Address = cx.argument::<JsString>(1)?.value(&mut cx).parse().unwrap();
+    let subgraph_deployment_id: B256 = cx.argument::<JsString>(2)?.value(&mut cx).parse().unwrap();
+    Ok(cx.boxed(AttestationVerifier::new(
+        U256::from(chain_id),
+        dispute_manager,
+        subgraph_deployment_id,
+    )))
+}
+
+fn attestation_verifier_recover(mut cx: FunctionContext) -> JsResult<JsString> {
+    let this = cx.argument::<JsBox<AttestationVerifier>>(0)?;
+    let request_cid: B256 = cx.argument::<JsString>(1)?.value(&mut cx).parse().unwrap();
+    let response_cid: B256 = cx.argument::<JsString>(2)?.value(&mut cx).parse().unwrap();
+    let v = cx.argument::<JsNumber>(3)?.value(&mut cx) as u8;
+    let r: B256 = cx.argument::<JsString>(4)?.value(&mut cx).parse().unwrap();
+    let s: B256 = cx.argument::<JsString>(5)?.value(&mut cx).parse().unwrap();
+
+    let attestation = Attestation {
+        request_cid,
+        response_cid,
+        subgraph_deployment_id: this.subgraph_deployment_id(),
+        v,
+        r,
+        s,
+    };
+
+    match this.recover_signer(&attestation) {
+        Ok(signer) => Ok(cx.string(signer.to_string())),
+        Err(err) => cx.throw_error(err),
+    }
+}
+
 #[neon::main]
 fn main(mut cx: ModuleContext) -> NeonResult<()> {
     cx.export_function("signature_verifier_new", signature_verifier_new)?;
     cx.export_function("signature_verifier_verify", signature_verifier_verify)?;
+    cx.export_function(
+        "signature_verifier_verify_batch",
+        signature_verifier_verify_batch,
+    )?;
+    cx.export_function(
+        "signature_verifier_verify_erc1271",
+        signature_verifier_verify_erc1271,
+    )?;
+    cx.export_function(
+        "signature_verifier_verify_erc6492",
+        signature_verifier_verify_erc6492,
+    )?;
     cx.export_function("attestation_signer_new", attestation_signer_new)?;
     cx.export_function(
         "attestation_signer_create_attestation",
         attestation_signer_create_attestation,
     )?;
+    cx.export_function("attestation_verifier_new", attestation_verifier_new)?;
+    cx.export_function("attestation_verifier_recover", attestation_verifier_recover)?;
     Ok(())
 }
@@ -1,54 +1,257 @@
+use std::convert::TryInto;
 use std::sync::Arc;
 
 use alloy_primitives::Address;
 use arc_swap::ArcSwap;
 use keccak_hash::keccak;
-use lazy_static::lazy_static;
 use neon::prelude::Finalize;
-use secp256k1::{ecdsa::RecoverableSignature, Message, PublicKey, Secp256k1, VerifyOnly};
 
-lazy_static! {
-    static ref SECP256K1: Secp256k1<VerifyOnly> = Secp256k1::verification_only();
-}
+use rayon::prelude::*;
+
+use crate::abi::{decode_erc6492_wrapper, encode_erc6492_validator_args, encode_is_valid_signature_call};
+use crate::backend::{self, PublicKey, RecoverableSignature};
+use crate::eth_call::RpcEthCaller;
+
+/// Below this many entries, `verify_batch` recovers/verifies sequentially;
+/// the overhead of handing work to the rayon pool isn't worth it for small
+/// batches.
+const BATCH_PARALLEL_THRESHOLD: usize = 32;
+
+/// The EIP-1271 magic value returned by a compliant `isValidSignature` call
+/// on success. By construction this is also the 4-byte selector of
+/// `isValidSignature(bytes32,bytes)`.
+const EIP1271_MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+/// The 32-byte suffix marking an ERC-6492 wrapped counterfactual signature.
+const ERC6492_MAGIC_SUFFIX: [u8; 32] = [
+    0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92,
+    0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92,
+];
+
+/// Creation bytecode of the stateless ERC-6492 "universal signature
+/// validator" helper contract. Given `(address signer, bytes32 hash, address
+/// factory, bytes factoryCalldata, bytes innerSignature)` as constructor
+/// arguments, it deploys the counterfactual wallet via `factory.call
+/// (factoryCalldata)`, calls `isValidSignature(hash, innerSignature)` on it,
+/// and returns the boolean result. It never gets deployed for real: the
+/// bytecode is only ever passed as `data` to a read-only `eth_call`.
+const UNIVERSAL_SIG_VALIDATOR_BYTECODE: &[u8] =
+    include_bytes!("contracts/universal_sig_validator.bin");
 
 enum Signer {
     PublicKey(PublicKey),
     Address(Address),
+    // An address confirmed to be an EIP-1271 smart-contract wallet, plus the
+    // RPC caller used to ask it to re-validate future signatures.
+    Contract(Address, Arc<RpcEthCaller>),
+    // An address confirmed to be an ERC-6492 counterfactual wallet.
+    Erc6492(Address),
+}
+
+/// Selects how a message is hashed before signature recovery/verification.
+pub enum HashingMode {
+    /// Bare `keccak256(message)`, the historical behavior of this module.
+    Raw,
+    /// The EIP-191 `personal_sign` prefixed form:
+    /// `keccak256("\x19Ethereum Signed Message:\n" || len(message) || message)`,
+    /// used by `personal_sign` and most browser wallet signing flows.
+    Eip191Personal,
+}
+
+fn hash_message(message: &[u8], mode: &HashingMode) -> [u8; 32] {
+    match mode {
+        HashingMode::Raw => keccak(message).to_fixed_bytes(),
+        HashingMode::Eip191Personal => {
+            let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
+            let mut preimage = Vec::with_capacity(prefix.len() + message.len());
+            preimage.extend_from_slice(prefix.as_bytes());
+            preimage.extend_from_slice(message);
+            keccak(&preimage).to_fixed_bytes()
+        }
+    }
+}
+
+fn address_of(public_key: &PublicKey) -> Address {
+    let point = backend::uncompressed_point(public_key);
+    debug_assert_eq!(point[0], 0x04);
+    Address::from_slice(&keccak(&point[1..])[12..])
+}
+
+/// Parses the `r || s || v` layout produced by the Neon layer into a
+/// [`RecoverableSignature`], accepting both the `{0, 1}` and `{27, 28}`
+/// conventions for `v`.
+///
+/// `signature` is not guaranteed to be 65 bytes at every call site (the
+/// ERC-6492 path strips a variable-length wrapper off an attacker-supplied
+/// signature before reaching here), so the length is checked rather than
+/// indexed into blindly. `r`/`s` are not guaranteed to be valid curve
+/// scalars either (e.g. out of range for the secp256k1 group order), so
+/// parsing them is fallible too.
+pub(crate) fn parse_recoverable_signature(signature: &[u8]) -> Result<RecoverableSignature, &'static str> {
+    if signature.len() != 65 {
+        return Err("Signature must be 65 bytes");
+    }
+    let recovery_id = match signature[64] {
+        id @ (0 | 1) => id,
+        id @ (27 | 28) => id - 27,
+        _ => return Err("Invalid recovery id"),
+    };
+    backend::recoverable_signature_from_parts(signature[..64].try_into().unwrap(), recovery_id)
+        .map_err(|_| "Invalid signature")
+}
+
+/// Serializes a recoverable ECDSA signature as the 65-byte `r || s || v`
+/// layout expected by on-chain `isValidSignature` implementations, with `v`
+/// in the `{27, 28}` form.
+fn serialize_for_contract(signature: &RecoverableSignature) -> [u8; 65] {
+    let (rs, recovery_id) = backend::recoverable_signature_parts(signature);
+    let mut out = [0u8; 65];
+    out[..64].copy_from_slice(&rs);
+    out[64] = recovery_id + 27;
+    out
 }
 
 impl SignatureVerifier {
     pub fn new(signer: Address) -> Self {
         Self {
-            signer: ArcSwap::from_pointee(Signer::Address(signer)),
+            signer: Arc::new(ArcSwap::from_pointee(Signer::Address(signer))),
         }
     }
 
+    /// Like [`SignatureVerifier::verify`], but additionally accepts
+    /// EIP-1271 smart-contract wallet signatures: if ECDSA recovery does not
+    /// match the stored `Address`, the signer is asked on-chain via
+    /// `isValidSignature(bytes32,bytes)` whether it considers the signature
+    /// valid.
+    pub fn verify_erc1271(
+        &self,
+        message: &[u8],
+        signature: &RecoverableSignature,
+        mode: HashingMode,
+        rpc_endpoint: &str,
+    ) -> Result<bool, &'static str> {
+        let digest = hash_message(message, &mode);
+
+        match self.signer.load().as_ref() {
+            Signer::PublicKey(signer) => Ok(backend::verify(&digest, signature, signer)),
+            Signer::Contract(addr, caller) => {
+                Self::call_is_valid_signature(caller, *addr, digest, signature)
+            }
+            Signer::Address(addr) | Signer::Erc6492(addr) => {
+                if let Ok(recovered) = backend::recover(&digest, signature) {
+                    if address_of(&recovered) == *addr {
+                        self.signer.store(Arc::new(Signer::PublicKey(recovered)));
+                        return Ok(true);
+                    }
+                }
+
+                let caller = Arc::new(RpcEthCaller::new(rpc_endpoint));
+                let valid = Self::call_is_valid_signature(&caller, *addr, digest, signature)?;
+                if valid {
+                    self.signer
+                        .store(Arc::new(Signer::Contract(*addr, caller)));
+                }
+                Ok(valid)
+            }
+        }
+    }
+
+    fn call_is_valid_signature(
+        caller: &RpcEthCaller,
+        signer: Address,
+        digest: [u8; 32],
+        signature: &RecoverableSignature,
+    ) -> Result<bool, &'static str> {
+        let calldata = encode_is_valid_signature_call(
+            EIP1271_MAGIC_VALUE,
+            digest,
+            &serialize_for_contract(signature),
+        );
+        let result = caller
+            .eth_call(Some(signer), calldata)
+            .map_err(|_| "EIP-1271 eth_call failed")?;
+        Ok(result.len() >= 4 && result[..4] == EIP1271_MAGIC_VALUE)
+    }
+
+    /// Like [`SignatureVerifier::verify_erc1271`], but additionally accepts
+    /// ERC-6492 wrapped signatures from wallets that have not been deployed
+    /// yet: if `signature` ends with the ERC-6492 magic suffix, the wrapped
+    /// `(factory, factoryCalldata, innerSignature)` is simulated in a single
+    /// `eth_call` that deploys the wallet and asks it to validate the inner
+    /// signature. Unwrapped signatures fall through to
+    /// [`SignatureVerifier::verify_erc1271`].
+    pub fn verify_erc6492(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        mode: HashingMode,
+        rpc_endpoint: &str,
+    ) -> Result<bool, &'static str> {
+        let Some(body) = signature
+            .len()
+            .checked_sub(ERC6492_MAGIC_SUFFIX.len())
+            .filter(|&split| signature[split..] == ERC6492_MAGIC_SUFFIX)
+            .map(|split| &signature[..split])
+        else {
+            return self.verify_erc1271(
+                message,
+                &parse_recoverable_signature(signature)?,
+                mode,
+                rpc_endpoint,
+            );
+        };
+
+        let (factory, factory_calldata, inner_signature) =
+            decode_erc6492_wrapper(body).ok_or("Malformed ERC-6492 signature")?;
+
+        let addr = match self.signer.load().as_ref() {
+            Signer::Address(addr) | Signer::Contract(addr, _) | Signer::Erc6492(addr) => *addr,
+            Signer::PublicKey(_) => return Err("Signer is a cached EOA public key"),
+        };
+
+        let digest = hash_message(message, &mode);
+        let mut calldata = UNIVERSAL_SIG_VALIDATOR_BYTECODE.to_vec();
+        calldata.extend(encode_erc6492_validator_args(
+            addr,
+            digest,
+            factory,
+            &factory_calldata,
+            &inner_signature,
+        ));
+
+        let result = RpcEthCaller::new(rpc_endpoint)
+            .eth_call(None, calldata)
+            .map_err(|_| "ERC-6492 eth_call failed")?;
+        let valid = result.last() == Some(&1u8);
+
+        if valid {
+            self.signer.store(Arc::new(Signer::Erc6492(addr)));
+        }
+
+        Ok(valid)
+    }
+
     pub fn verify(
         &self,
         message: &[u8],
         signature: &RecoverableSignature,
+        mode: HashingMode,
     ) -> Result<bool, &'static str> {
-        let message = Message::from_slice(&keccak(message).to_fixed_bytes()).unwrap();
+        let digest = hash_message(message, &mode);
 
         match self.signer.load().as_ref() {
             // If we already have the public key we can do the fast path.
-            Signer::PublicKey(signer) => Ok(SECP256K1
-                .verify_ecdsa(&message, &signature.to_standard(), signer)
-                .is_ok()),
+            Signer::PublicKey(signer) => Ok(backend::verify(&digest, signature, signer)),
             // If we don't have the public key, but have the address instead
             // we derive the address from the recovered key. If it's a match
             // then we can save the public key for the next time avoiding
             // running keccak on every verification and using the much faster
             // verify method instead of the slow recover method.
             Signer::Address(addr) => {
-                let recovered_signer = SECP256K1
-                    .recover_ecdsa(&message, signature)
+                let recovered_signer = backend::recover(&digest, signature)
                     .map_err(|_| "Failed to recover signature")?;
 
-                let ser = recovered_signer.serialize_uncompressed();
-                debug_assert_eq!(ser[0], 0x04);
-                let pk_hash = keccak(&ser[1..]);
-                let equal = pk_hash[12..] == addr;
+                let equal = address_of(&recovered_signer) == *addr;
 
                 if equal {
                     self.signer
@@ -57,12 +260,85 @@ impl SignatureVerifier {
 
                 Ok(equal)
             }
+            // A contract signer was confirmed via `verify_erc1271`/
+            // `verify_erc6492`; plain `verify` has no chain access to ask it
+            // again.
+            Signer::Contract(..) => Err("Signer is a smart contract; use verify_erc1271"),
+            Signer::Erc6492(..) => Err("Signer is a counterfactual wallet; use verify_erc6492"),
+        }
+    }
+
+    /// Verifies many `(message, signature)` pairs against this signer in one
+    /// call, amortizing the FFI boundary crossing over the whole batch.
+    /// Recovery only has to happen once: the first entry populates the
+    /// cached [`Signer::PublicKey`] (same as [`SignatureVerifier::verify`]),
+    /// and every other entry in the batch reuses it through the fast
+    /// `backend::verify` path. Batches at or above
+    /// [`BATCH_PARALLEL_THRESHOLD`] are verified across a rayon thread pool.
+    pub fn verify_batch(
+        &self,
+        messages: &[&[u8]],
+        signatures: &[RecoverableSignature],
+        mode: HashingMode,
+    ) -> Result<Vec<bool>, &'static str> {
+        if messages.len() != signatures.len() {
+            return Err("messages and signatures must have the same length");
+        }
+
+        match self.signer.load().as_ref() {
+            Signer::Contract(..) => return Err("Signer is a smart contract; use verify_erc1271"),
+            Signer::Erc6492(..) => return Err("Signer is a counterfactual wallet; use verify_erc6492"),
+            Signer::PublicKey(_) | Signer::Address(_) => {}
+        }
+
+        if let (Signer::Address(addr), Some(message), Some(signature)) = (
+            self.signer.load().as_ref(),
+            messages.first(),
+            signatures.first(),
+        ) {
+            let digest = hash_message(message, &mode);
+            if let Ok(recovered) = backend::recover(&digest, signature) {
+                if address_of(&recovered) == *addr {
+                    self.signer.store(Arc::new(Signer::PublicKey(recovered)));
+                }
+            }
+        }
+
+        // `self.signer` is shared (via the `Arc<ArcSwap<_>>` cache cell) with
+        // any `SignatureVerifier` clone a concurrent `verify_erc1271`/
+        // `verify_erc6492` call is running on a background thread, so the
+        // signer can be upgraded to `Contract`/`Erc6492` mid-batch; that
+        // case is reported as an `Err`, not treated as unreachable.
+        let verify_one = |(message, signature): (&&[u8], &RecoverableSignature)| -> Result<bool, &'static str> {
+            let digest = hash_message(message, &mode);
+            match self.signer.load().as_ref() {
+                Signer::PublicKey(signer) => Ok(backend::verify(&digest, signature, signer)),
+                Signer::Address(addr) => Ok(backend::recover(&digest, signature)
+                    .map(|recovered| address_of(&recovered) == *addr)
+                    .unwrap_or(false)),
+                Signer::Contract(..) => Err("Signer is a smart contract; use verify_erc1271"),
+                Signer::Erc6492(..) => Err("Signer is a counterfactual wallet; use verify_erc6492"),
+            }
+        };
+
+        let pairs = messages.iter().zip(signatures);
+        if messages.len() >= BATCH_PARALLEL_THRESHOLD {
+            pairs.collect::<Vec<_>>().into_par_iter().map(verify_one).collect()
+        } else {
+            pairs.map(verify_one).collect()
         }
     }
 }
 
+/// `signer` is behind an `Arc` so that `Clone` is a cheap pointer copy that
+/// shares the same cache cell, rather than a disconnected snapshot: the
+/// background thread `verify_erc1271`/`verify_erc6492` move a clone onto
+/// (see `lib.rs`) needs its cache writes (e.g. confirming a contract
+/// signer) to be visible to the original `SignatureVerifier` held by the JS
+/// side, otherwise every call would repeat the `eth_call` round trip.
+#[derive(Clone)]
 pub struct SignatureVerifier {
-    signer: ArcSwap<Signer>,
+    signer: Arc<ArcSwap<Signer>>,
 }
 
 impl Finalize for SignatureVerifier {}